@@ -1,7 +1,35 @@
-use std::io::Write;
+use std::fmt;
+use std::io::{self, Write};
 use std::process::{Command, Stdio};
 use std::env;
 
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{generate, Shell};
+use serde::Deserialize;
+
+// Unified error type for the fallible parts of the program.
+enum AppError {
+    Io(io::Error),
+    Other(String),
+}
+
+type AppResult<T> = Result<T, AppError>;
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Io(err) => write!(f, "{}", err),
+            AppError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
 // ANSI color codes as constants
 const BOLD: &str = "\x1B[1m";
 const BLUE: &str = "\x1B[34m";
@@ -9,43 +37,364 @@ const RED: &str = "\x1B[31m";
 const GREEN: &str = "\x1B[32m";
 const RESET: &str = "\x1B[0m";
 
+// A package list as (name, description) pairs.
+type PackageList = Vec<(String, String)>;
+// Results grouped by backend, in (pacman, aur, flatpak) order.
+type CategorizedResults = (PackageList, PackageList, PackageList);
+
+// User configuration loaded from ~/.config/pd/config.toml at startup. Every
+// field has a default so a missing or partial file still yields a usable config.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    backends: Backends,
+    colors: Colors,
+    // Default output mode when --merged is not passed on the command line.
+    merged: bool,
+    pager: Pager,
+}
+
+// Which backends to query. Users can turn off the ones they don't use.
+#[derive(Deserialize)]
+#[serde(default)]
+struct Backends {
+    pacman: bool,
+    aur: bool,
+    flatpak: bool,
+}
+
+// ANSI color code used for each source's colored output.
+#[derive(Deserialize)]
+#[serde(default)]
+struct Colors {
+    pacman: String,
+    aur: String,
+    flatpak: String,
+}
+
+// The pager command and its arguments.
+#[derive(Deserialize)]
+#[serde(default)]
+struct Pager {
+    command: String,
+    args: Vec<String>,
+}
+
+impl Default for Backends {
+    fn default() -> Self {
+        Backends { pacman: true, aur: true, flatpak: true }
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            pacman: BLUE.to_string(),
+            aur: RED.to_string(),
+            flatpak: GREEN.to_string(),
+        }
+    }
+}
+
+impl Default for Pager {
+    fn default() -> Self {
+        Pager {
+            command: "less".to_string(),
+            args: vec!["-R".to_string(), "+Gg".to_string(), "-~".to_string()],
+        }
+    }
+}
+
+impl Config {
+    // Load the config file, falling back to defaults if it is absent or invalid.
+    fn load() -> Config {
+        let path = match env::var_os("HOME") {
+            Some(home) => std::path::Path::new(&home).join(".config/pd/config.toml"),
+            None => return Config::default(),
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_default(),
+            Err(_) => Config::default(),
+        }
+    }
+
+    // The configured color for a given source.
+    fn color(&self, source: Source) -> &str {
+        match source {
+            Source::Pacman => &self.colors.pacman,
+            Source::Aur => &self.colors.aur,
+            Source::Flatpak => &self.colors.flatpak,
+        }
+    }
+}
+
+// Origin of a single result, used to keep provenance when categories are merged.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Pacman,
+    Aur,
+    Flatpak,
+}
+
+// How the AUR should be searched, mapping to the RPC `by` parameter.
+#[derive(Clone, Copy, ValueEnum)]
+enum SearchBy {
+    Name,
+    NameDesc,
+    Maintainer,
+    Depends,
+    Provides,
+}
+
+impl SearchBy {
+    // The RPC `by` value for this mode.
+    fn as_rpc(self) -> &'static str {
+        match self {
+            SearchBy::Name => "name",
+            SearchBy::NameDesc => "name-desc",
+            SearchBy::Maintainer => "maintainer",
+            SearchBy::Depends => "depends",
+            SearchBy::Provides => "provides",
+        }
+    }
+
+    // Whether the search term denotes a package name, so ranking results by
+    // name similarity is meaningful.
+    fn ranks_by_name(self) -> bool {
+        matches!(self, SearchBy::Name | SearchBy::NameDesc)
+    }
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Source::Pacman => "pacman",
+            Source::Aur => "aur",
+            Source::Flatpak => "flatpak",
+        }
+    }
+}
+
+// Command-line interface for pd, searching Arch repos, the AUR, and flatpak.
+#[derive(Parser)]
+#[command(name = "pd", about = "Search pacman, the AUR, and flatpak for packages")]
+struct Args {
+    /// Interleave all backends into a single relevance-sorted list
+    #[arg(long)]
+    merged: bool,
+
+    /// How to search the AUR
+    #[arg(long, value_enum, default_value_t = SearchBy::NameDesc)]
+    by: SearchBy,
+
+    /// Search terms (joined with spaces)
+    #[arg(trailing_var_arg = true)]
+    terms: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<SubCommand>,
+}
+
+#[derive(Subcommand)]
+enum SubCommand {
+    /// Generate a shell completion script and print it to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 2 {
-        eprintln!("Usage: pd <search-term>");
+    if let Err(err) = run() {
+        eprintln!("pd: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> AppResult<()> {
+    let args = Args::parse();
+
+    if let Some(SubCommand::Completions { shell }) = args.command {
+        let mut cmd = Args::command();
+        generate(shell, &mut cmd, "pd", &mut io::stdout());
+        return Ok(());
+    }
+
+    if args.terms.is_empty() {
+        eprintln!("Usage: pd [--merged] [--by <name|name-desc|maintainer|depends|provides>] <search-term>");
         std::process::exit(1);
     }
 
-    // Use all arguments after the program name as the search term
-    let search_term = args[1..].join(" ");
-    let results = search_packages(&search_term);
-    print_results_with_pager(&results);
+    let config = Config::load();
+    // A --merged flag or the configured default both enable merged output.
+    let merged = args.merged || config.merged;
+
+    let search_term = args.terms.join(" ");
+    let results = search_packages(&search_term, args.by, &config);
+    let selectable = print_results_with_pager(&search_term, &results, merged, &config)?;
+    prompt_and_install(&selectable);
+    Ok(())
 }
 
-fn search_packages(term: &str) -> (Vec<(String, String)>, Vec<(String, String)>, Vec<(String, String)>) {
+// Weight applied to the description match when blending it with the name score.
+const DESCRIPTION_WEIGHT: f64 = 0.25;
+
+fn search_packages(term: &str, by: SearchBy, config: &Config) -> CategorizedResults {
+    // Run a backend only if it is enabled; on failure print a single warning
+    // for that source and fall back to an empty list so the others still show.
+    fn degrade(
+        source: &str,
+        enabled: bool,
+        search: impl FnOnce() -> AppResult<PackageList>,
+    ) -> Vec<(String, String)> {
+        if !enabled {
+            return Vec::new();
+        }
+        match search() {
+            Ok(results) => results,
+            Err(err) => {
+                eprintln!("warning: {} search failed: {}", source, err);
+                Vec::new()
+            }
+        }
+    }
+
+    let pacman = degrade("pacman", config.backends.pacman, || search_pacman(term));
+    let aur = degrade("aur", config.backends.aur, || search_aur(term, by));
+    let flatpak = degrade("flatpak", config.backends.flatpak, || search_flatpak(term));
+
+    // The term only describes a package name in the name-based modes; ranking
+    // AUR hits by name similarity is meaningless when searching by maintainer,
+    // dependency, or provides, so leave those in the backend's own order.
+    let aur = if by.ranks_by_name() { rank_results(term, aur) } else { aur };
+
     (
-        search_pacman(term),
-        search_aur(term),
-        search_flatpak(term),
+        rank_results(term, pacman),
+        aur,
+        rank_results(term, flatpak),
     )
 }
 
-fn search_pacman(term: &str) -> Vec<(String, String)> {
+// Build the multiset of adjacent lowercased character bigrams of a string.
+fn bigrams(s: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = s.to_lowercase().chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+// Sørensen–Dice coefficient over character bigrams: 2 * |A ∩ B| / (|A| + |B|).
+// Strings shorter than two characters are handled as a special case where an
+// exact match scores 1.0 and anything else 0.0.
+fn dice_coefficient(a: &str, b: &str) -> f64 {
+    let a_lower = a.to_lowercase();
+    let b_lower = b.to_lowercase();
+
+    if a_lower.chars().count() < 2 || b_lower.chars().count() < 2 {
+        return if a_lower == b_lower { 1.0 } else { 0.0 };
+    }
+
+    let a_bigrams = bigrams(a);
+    let mut b_bigrams = bigrams(b);
+
+    let total = a_bigrams.len() + b_bigrams.len();
+    let mut intersection = 0usize;
+    for bigram in a_bigrams {
+        if let Some(pos) = b_bigrams.iter().position(|&bg| bg == bigram) {
+            b_bigrams.swap_remove(pos);
+            intersection += 1;
+        }
+    }
+
+    2.0 * intersection as f64 / total as f64
+}
+
+// Score a (name, description) pair against the search term: the name match
+// dominates, with a smaller weighted contribution from the description.
+fn relevance_score(term: &str, name: &str, description: &str) -> f64 {
+    let name_score = dice_coefficient(term, name);
+    let description_score = dice_coefficient(term, description);
+    name_score + DESCRIPTION_WEIGHT * description_score
+}
+
+// Sort a category's results best-first by their relevance to the term.
+fn rank_results(term: &str, mut results: Vec<(String, String)>) -> Vec<(String, String)> {
+    results.sort_by(|(a_name, a_desc), (b_name, b_desc)| {
+        let a = relevance_score(term, a_name, a_desc);
+        let b = relevance_score(term, b_name, b_desc);
+        b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results
+}
+
+fn search_pacman(term: &str) -> AppResult<PackageList> {
     execute_search_command("pacman", &["-Ss", term])
 }
 
-fn search_aur(term: &str) -> Vec<(String, String)> {
-    execute_search_command("yay", &["-Ss", "--aur", term])
+// The AUR RPC search endpoint, versioned per the aurweb API.
+const AUR_RPC_URL: &str = "https://aur.archlinux.org/rpc/?v=5&type=search";
+
+// Subset of an AUR RPC search response envelope.
+#[derive(Deserialize)]
+struct AurResponse {
+    #[serde(default)]
+    results: Vec<AurPackage>,
+}
+
+// A single package record from the AUR RPC `results` array. Only the fields the
+// pipeline consumes are kept; the RPC returns more (Version, NumVotes, …) which
+// serde ignores by default.
+#[derive(Deserialize)]
+struct AurPackage {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Description")]
+    description: Option<String>,
+}
+
+fn search_aur(term: &str, by: SearchBy) -> AppResult<PackageList> {
+    let url = format!("{}&by={}&arg={}", AUR_RPC_URL, by.as_rpc(), encode_query(term));
+
+    let body = ureq::get(&url)
+        .call()
+        .map_err(|err| AppError::Other(format!("AUR RPC request failed: {}", err)))?
+        .into_string()?;
+
+    let parsed: AurResponse = serde_json::from_str(&body)
+        .map_err(|err| AppError::Other(format!("could not parse AUR RPC response: {}", err)))?;
+
+    Ok(parsed
+        .results
+        .into_iter()
+        .map(|pkg| {
+            let description = pkg
+                .description
+                .filter(|d| !d.trim().is_empty())
+                .unwrap_or_else(|| "No description.".to_string());
+            (pkg.name, description)
+        })
+        .collect())
+}
+
+// Percent-encode the characters that would otherwise break the RPC query string.
+fn encode_query(term: &str) -> String {
+    let mut encoded = String::with_capacity(term.len());
+    for byte in term.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char);
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
 }
 
-fn search_flatpak(term: &str) -> Vec<(String, String)> {
+fn search_flatpak(term: &str) -> AppResult<PackageList> {
     let output = Command::new("flatpak")
-        .args(&["search", term])
-        .output()
-        .expect("Failed to execute flatpak command");
+        .args(["search", term])
+        .output()?;
 
-    String::from_utf8_lossy(&output.stdout)
+    Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
         .skip(1)
         .filter(|line| !line.is_empty())
@@ -60,16 +409,13 @@ fn search_flatpak(term: &str) -> Vec<(String, String)> {
                 None
             }
         })
-        .collect()
+        .collect())
 }
 
-fn execute_search_command(command: &str, args: &[&str]) -> Vec<(String, String)> {
-    let output = Command::new(command)
-        .args(args)
-        .output()
-        .unwrap_or_else(|_| panic!("Failed to execute {} command", command));
+fn execute_search_command(command: &str, args: &[&str]) -> AppResult<PackageList> {
+    let output = Command::new(command).args(args).output()?;
 
-    String::from_utf8_lossy(&output.stdout)
+    Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
         .collect::<Vec<&str>>()
         .chunks(2)
@@ -95,22 +441,31 @@ fn execute_search_command(command: &str, args: &[&str]) -> Vec<(String, String)>
                 None
             }
         })
-        .collect()
+        .collect())
 }
 
-fn print_results_with_pager(results: &(Vec<(String, String)>, Vec<(String, String)>, Vec<(String, String)>)) {
+// Print the results through the pager and return the flat, origin-tagged list
+// in display order so the caller can offer install-by-number selection.
+fn print_results_with_pager(
+    term: &str,
+    results: &CategorizedResults,
+    merged: bool,
+    config: &Config,
+) -> AppResult<Vec<(Source, String)>> {
     let (pacman, aur, flatpak) = results;
-    
+
     let mut output = String::new();
-    
+    // Numbered, origin-tagged list matching the order packages are printed.
+    let mut selectable: Vec<(Source, String)> = Vec::new();
+
     fn format_package_count(count: usize) -> String {
         if count == 1 {
-            format!("1 package")
+            "1 package".to_string()
         } else {
             format!("{} packages", count)
         }
     }
-    
+
     // Summary of results
     output.push_str(&format!("{}Pacman:{} {} | {}AUR:{} {} | {}Flatpak:{} {}\n\n",
         BOLD, RESET, format_package_count(pacman.len()),
@@ -118,33 +473,163 @@ fn print_results_with_pager(results: &(Vec<(String, String)>, Vec<(String, Strin
         BOLD, RESET, format_package_count(flatpak.len())
     ));
 
-    fn print_category_results(output: &mut String, category_name: &str, results: &[(String, String)], color: &str) {
+    if merged {
+        // Tag every result with its origin, then sort the combined vector so the
+        // single most relevant match floats to the top regardless of backend.
+        let mut combined: Vec<(Source, String, String)> = Vec::new();
+        for (source, category) in [
+            (Source::Pacman, pacman),
+            (Source::Aur, aur),
+            (Source::Flatpak, flatpak),
+        ] {
+            for (package, description) in category {
+                combined.push((source, package.clone(), description.clone()));
+            }
+        }
+        combined.sort_by(|(_, a_name, a_desc), (_, b_name, b_desc)| {
+            let a = relevance_score(term, a_name, a_desc);
+            let b = relevance_score(term, b_name, b_desc);
+            b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        if !combined.is_empty() {
+            output.push_str(&format!("{}Merged Results:{}\n", BOLD, RESET));
+            output.push_str(&format!("{}\n", "=".repeat(15)));
+            for (source, package, description) in &combined {
+                let color = config.color(*source);
+                selectable.push((*source, package.clone()));
+                output.push_str(&format!(
+                    "{}[{}]{} {}{}[{}]{} {}{}{}{}\n",
+                    BOLD, selectable.len(), RESET,
+                    BOLD, color, source.label(), RESET,
+                    BOLD, color, package, RESET
+                ));
+                output.push_str(&format!("  {}\n\n", description));
+            }
+        }
+
+        print_output_with_pager(&output, config)?;
+        return Ok(selectable);
+    }
+
+    fn print_category_results(
+        output: &mut String,
+        selectable: &mut Vec<(Source, String)>,
+        category_name: &str,
+        source: Source,
+        results: &[(String, String)],
+        color: &str,
+    ) {
         if !results.is_empty() {
             output.push_str(&format!("{}{} Results:{}\n", BOLD, category_name, RESET));
             output.push_str(&format!("{}\n", "=".repeat(category_name.len() + 9)));
             for (package, description) in results {
-                output.push_str(&format!("{}{}{}{}\n", BOLD, color, package, RESET));
+                selectable.push((source, package.clone()));
+                output.push_str(&format!(
+                    "{}[{}]{} {}{}{}{}\n",
+                    BOLD, selectable.len(), RESET, BOLD, color, package, RESET
+                ));
                 output.push_str(&format!("  {}\n\n", description));
             }
         }
     }
 
-    print_category_results(&mut output, "Pacman", pacman, BLUE);
-    print_category_results(&mut output, "AUR", aur, RED);
-    print_category_results(&mut output, "Flatpak", flatpak, GREEN);
+    print_category_results(&mut output, &mut selectable, "Pacman", Source::Pacman, pacman, config.color(Source::Pacman));
+    print_category_results(&mut output, &mut selectable, "AUR", Source::Aur, aur, config.color(Source::Aur));
+    print_category_results(&mut output, &mut selectable, "Flatpak", Source::Flatpak, flatpak, config.color(Source::Flatpak));
+
+    print_output_with_pager(&output, config)?;
+    Ok(selectable)
+}
+
+// Prompt the user to pick packages by number and install each via its backend.
+fn prompt_and_install(selectable: &[(Source, String)]) {
+    if selectable.is_empty() {
+        return;
+    }
+
+    print!("\nEnter package numbers to install (space-separated), or press Enter to skip: ");
+    std::io::stdout().flush().ok();
+
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line).is_err() {
+        return;
+    }
+
+    let selections: Vec<usize> = line
+        .split_whitespace()
+        .filter_map(|token| token.parse::<usize>().ok())
+        .filter(|&n| n >= 1 && n <= selectable.len())
+        .collect();
+
+    for index in selections {
+        let (source, package) = &selectable[index - 1];
+        install_package(*source, package);
+    }
+}
+
+// Dispatch the install of a single package to the backend it came from,
+// elevating privileges for repository packages that pacman installs system-wide.
+fn install_package(source: Source, package: &str) {
+    let status = match source {
+        Source::Pacman => Command::new("sudo").args(["pacman", "-S", package]).status(),
+        Source::Aur => Command::new("yay").args(["-S", package]).status(),
+        Source::Flatpak => Command::new("flatpak").args(["install", package]).status(),
+    };
 
+    if let Err(err) = status {
+        eprintln!("Failed to install {}: {}", package, err);
+    }
+}
+
+fn print_output_with_pager(output: &str, config: &Config) -> AppResult<()> {
     // Replace all '~' characters with spaces
     let display_output = output.replace('~', " ");
 
-    let mut pager = Command::new("less")
-        .args(&["-R", "+Gg", "-~"]) // Added the "-~" option to suppress ~ symbols
+    let mut pager = Command::new(&config.pager.command)
+        .args(&config.pager.args)
         .stdin(Stdio::piped())
         .spawn()
-        .expect("Failed to start pager");
+        .map_err(|err| AppError::Other(format!("failed to start pager '{}': {}", config.pager.command, err)))?;
 
     if let Some(mut pager_stdin) = pager.stdin.take() {
-        pager_stdin.write_all(display_output.as_bytes()).expect("Failed to write to pager");
+        pager_stdin.write_all(display_output.as_bytes())?;
+    }
+
+    pager.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_strings_score_one() {
+        assert_eq!(dice_coefficient("firefox", "firefox"), 1.0);
     }
 
-    pager.wait().expect("Pager process wasn't running");
+    #[test]
+    fn disjoint_strings_score_zero() {
+        assert_eq!(dice_coefficient("abc", "xyz"), 0.0);
+    }
+
+    #[test]
+    fn short_strings_match_exactly_or_not_at_all() {
+        // Fewer than two characters falls back to exact-match comparison.
+        assert_eq!(dice_coefficient("a", "a"), 1.0);
+        assert_eq!(dice_coefficient("a", "b"), 0.0);
+        assert_eq!(dice_coefficient("a", "ab"), 0.0);
+    }
+
+    #[test]
+    fn partial_overlap_scores_between_zero_and_one() {
+        let score = dice_coefficient("night", "nacht");
+        assert!(score > 0.0 && score < 1.0);
+    }
+
+    #[test]
+    fn scoring_is_case_insensitive() {
+        assert_eq!(dice_coefficient("Firefox", "firefox"), 1.0);
+    }
 }